@@ -1,11 +1,13 @@
 use hashbrown::HashSet;
 use std::collections::HashMap;
+use std::fs::File;
 use std::hash::{BuildHasher, BuildHasherDefault, Hash};
 use twox_hash::XxHash64;
 
 use std::f64::consts::TAU;
 
-use image::{ImageBuffer, RgbImage};
+use image::codecs::gif::GifEncoder;
+use image::{Frame, ImageBuffer, RgbImage, RgbaImage};
 use rand::prelude::*;
 
 type Color = [u8; 3];
@@ -14,10 +16,18 @@ type ColorBase = [u8; 3];
 fn color_base_to_color(cb: ColorBase, color_size: u64) -> Color {
     cb.map(|cbc| (cbc as u64 * 255 / (color_size - 1)) as u8)
 }
-type ColorOffset = [i16; 3];
 type Location = [usize; 2];
 
-fn make_bases_offsets<R: Rng>(scale: u64, rng: &mut R) -> (Vec<ColorBase>, Vec<ColorOffset>) {
+// How the color cube is walked for insertion into the spiral.
+enum ColorOrder {
+    // Random order; scatters similar colors across the image.
+    Shuffle,
+    // Order along a 3D Hilbert space-filling curve, so consecutively
+    // inserted colors are spatial neighbors in RGB.
+    Hilbert,
+}
+
+fn make_bases<R: Rng>(scale: u64, order: ColorOrder, rng: &mut R) -> Vec<ColorBase> {
     let color_size = scale.pow(2);
     let mut color_bases: Vec<ColorBase> = (0..scale.pow(6))
         .map(|n| {
@@ -27,27 +37,298 @@ fn make_bases_offsets<R: Rng>(scale: u64, rng: &mut R) -> (Vec<ColorBase>, Vec<C
             [r_base as u8, g_base as u8, b_base as u8]
         })
         .collect();
-    let mut color_offsets: Vec<ColorOffset> = color_bases
-        .iter()
-        .map(|color| color.map(|c| c as i16))
-        .flat_map(|color| {
-            vec![
-                [color[0], color[1], color[2]],
-                [color[0], color[1], -color[2]],
-                [color[0], -color[1], color[2]],
-                [color[0], -color[1], -color[2]],
-                [-color[0], color[1], color[2]],
-                [-color[0], color[1], -color[2]],
-                [-color[0], -color[1], color[2]],
-                [-color[0], -color[1], -color[2]],
-            ]
-            .into_iter()
-        })
-        .collect();
-    color_bases.shuffle(rng);
-    color_offsets
-        .sort_by_key(|color_offset| color_offset.map(|c| (c as i64).pow(2)).iter().sum::<i64>());
-    (color_bases, color_offsets)
+    match order {
+        ColorOrder::Shuffle => color_bases.shuffle(rng),
+        ColorOrder::Hilbert => {
+            let bits = u64::BITS - (color_size - 1).leading_zeros();
+            color_bases.sort_by_key(|cb| hilbert_index_3(bits, cb.map(|c| c as u32)));
+        }
+    }
+    color_bases
+}
+
+// Coordinate -> Hilbert-index transform for a 3D curve over `bits`-bit
+// axes, via Skilling's method: undo the per-level axis exchanges that
+// built up the transposed (Chakrabarti-Mehlhorn) representation, Gray-code
+// the result, then interleave the bits of the three axes (axis 0 most
+// significant within each bit-group) into a single linear distance.
+fn hilbert_index_3(bits: u32, mut x: [u32; 3]) -> u64 {
+    let mut q = 1u32 << (bits - 1);
+    while q > 1 {
+        let p = q - 1;
+        for i in 0..3 {
+            if x[i] & q != 0 {
+                x[0] ^= p;
+            } else {
+                let t = (x[0] ^ x[i]) & p;
+                x[0] ^= t;
+                x[i] ^= t;
+            }
+        }
+        q >>= 1;
+    }
+    for i in 1..3 {
+        x[i] ^= x[i - 1];
+    }
+    let mut t = 0;
+    q = 1 << (bits - 1);
+    while q > 1 {
+        if x[2] & q != 0 {
+            t ^= q - 1;
+        }
+        q >>= 1;
+    }
+    for xi in x.iter_mut() {
+        *xi ^= t;
+    }
+    let mut index: u64 = 0;
+    for b in (0..bits).rev() {
+        for &xi in &x {
+            index = (index << 1) | ((xi >> b) & 1) as u64;
+        }
+    }
+    index
+}
+
+// Which space "most similar" is measured in when querying the k-d forest.
+#[derive(Clone, Copy)]
+enum ColorMetric {
+    // Raw color-cube coordinates; cheap but doesn't match human perception.
+    Rgb,
+    // Oklab, a perceptually uniform space, so equal distances look equally
+    // similar and luminance jumps don't band.
+    Oklab,
+}
+
+fn color_base_to_metric_point(color_base: ColorBase, color_size: u64, metric: ColorMetric) -> KdPoint {
+    cube_point_to_metric(color_base.map(|c| c as f64), color_size, metric)
+}
+
+// As `color_base_to_metric_point`, but for a continuous point in cube
+// coordinates rather than an exact, already-placed `ColorBase` — used to
+// project a frontier cell's running-mean color into metric space.
+fn cube_point_to_metric(point: [f64; 3], color_size: u64, metric: ColorMetric) -> KdPoint {
+    match metric {
+        ColorMetric::Rgb => point,
+        ColorMetric::Oklab => {
+            let rgb255 = point.map(|c| c * 255.0 / (color_size - 1) as f64);
+            oklab_from_rgb(rgb255)
+        }
+    }
+}
+
+fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// sRGB -> Oklab (Björn Ottosson's method): linearize, project into an LMS
+// cone-response space, cube-root to compress dynamic range, then rotate
+// into the final L/a/b axes. `rgb255` channels are continuous, in 0..255.
+fn oklab_from_rgb(rgb255: [f64; 3]) -> [f64; 3] {
+    let [r, g, b] = rgb255.map(|c| srgb_to_linear(c / 255.0));
+    let l = 0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b;
+    let m = 0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b;
+    let s = 0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b;
+    let (l_, m_, s_) = (l.cbrt(), m.cbrt(), s.cbrt());
+    [
+        0.2104542553 * l_ + 0.7936177850 * m_ - 0.0040720468 * s_,
+        1.9779984951 * l_ - 2.4285922050 * m_ + 0.4505937099 * s_,
+        0.0259040371 * l_ + 0.7827717662 * m_ - 0.8086757660 * s_,
+    ]
+}
+
+// Where the initial spiral direction of a seed or a randomly placed
+// fallback pixel comes from. Pixels placed by the spiral walk take the
+// walk's current direction instead.
+enum DirectionField {
+    // Independent uniform-random angle per pixel.
+    Random,
+    // A coherent angle sampled from a Perlin turbulence field, so the whole
+    // image develops flowing, swirling macro-structure.
+    Perlin,
+}
+
+fn sample_initial_dir<R: Rng>(
+    perlin_field: &Option<PerlinField>,
+    loc: Location,
+    size: usize,
+    rng: &mut R,
+) -> f64 {
+    match perlin_field {
+        None => rng.gen_range(0.0..TAU),
+        Some(field) => field.angle_at(loc[0] as f64 / size as f64, loc[1] as f64 / size as f64),
+    }
+}
+
+// Classic 2D Perlin noise, summed over several octaves (fractal/turbulence
+// noise). Each octave is its own gradient lattice, `lacunarity` times finer
+// than the last; octave amplitudes shrink by `persistence` each step.
+struct PerlinField {
+    octaves: Vec<Vec<Vec<[f64; 2]>>>,
+}
+
+impl PerlinField {
+    const LACUNARITY: usize = 2;
+    const PERSISTENCE: f64 = 0.5;
+
+    fn new<R: Rng>(rng: &mut R, num_octaves: usize, base_cells: usize) -> Self {
+        let mut cells = base_cells;
+        let octaves = (0..num_octaves)
+            .map(|_| {
+                let grid = (0..=cells)
+                    .map(|_| {
+                        (0..=cells)
+                            .map(|_| {
+                                let angle = rng.gen_range(0.0..TAU);
+                                [angle.cos(), angle.sin()]
+                            })
+                            .collect()
+                    })
+                    .collect();
+                cells *= Self::LACUNARITY;
+                grid
+            })
+            .collect();
+        Self { octaves }
+    }
+
+    // Angle in [0, TAU) of the turbulence field at (x, y), each in [0, 1).
+    fn angle_at(&self, x: f64, y: f64) -> f64 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut max_amplitude = 0.0;
+        for grid in &self.octaves {
+            sum += amplitude * Self::octave_noise(grid, x, y);
+            max_amplitude += amplitude;
+            amplitude *= Self::PERSISTENCE;
+        }
+        ((sum / max_amplitude + 1.0) / 2.0) * TAU
+    }
+
+    fn octave_noise(grid: &[Vec<[f64; 2]>], x: f64, y: f64) -> f64 {
+        let cells = grid.len() - 1;
+        let (gx, gy) = (x * cells as f64, y * cells as f64);
+        let (x0, y0) = (gx.floor() as usize, gy.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(cells), (y0 + 1).min(cells));
+        let (tx, ty) = (gx - x0 as f64, gy - y0 as f64);
+        let fade = |t: f64| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+        let dot = |cx: usize, cy: usize, dx: f64, dy: f64| {
+            let [gx, gy] = grid[cx][cy];
+            gx * dx + gy * dy
+        };
+        let n00 = dot(x0, y0, tx, ty);
+        let n10 = dot(x1, y0, tx - 1.0, ty);
+        let n01 = dot(x0, y1, tx, ty - 1.0);
+        let n11 = dot(x1, y1, tx - 1.0, ty - 1.0);
+        let (u, v) = (fade(tx), fade(ty));
+        let nx0 = n00 + u * (n10 - n00);
+        let nx1 = n01 + u * (n11 - n01);
+        nx0 + v * (nx1 - nx0)
+    }
+}
+
+// A k-d forest over already-placed colors in metric space, supporting
+// cheap incremental insertion and exact nearest-neighbor queries.
+//
+// The forest is a `Vec` of balanced static k-d trees whose sizes are
+// distinct powers of two, mirroring the bits of a binary counter: to
+// insert the Nth point, the trees matching the low set bits of N are torn
+// down, their points merged with the new one, and a single balanced tree
+// is rebuilt in their place. This keeps every tree perfectly balanced,
+// and each point is carried into a rebuilt tree at most about log2(n) times.
+type KdPoint = [f64; 3];
+
+struct KdNode {
+    point: KdPoint,
+    loc: Location,
+    axis: usize,
+    left: Option<Box<KdNode>>,
+    right: Option<Box<KdNode>>,
+}
+
+fn kd_squared_dist(a: KdPoint, b: KdPoint) -> f64 {
+    array_zip(a, b).map(|(x, y)| (x - y).powi(2)).iter().sum()
+}
+
+fn build_kd_tree(mut points: Vec<(KdPoint, Location)>) -> Box<KdNode> {
+    let spreads: [f64; 3] = std::array::from_fn(|axis| {
+        let (min, max) = points.iter().fold((f64::MAX, f64::MIN), |(min, max), (p, _)| {
+            (min.min(p[axis]), max.max(p[axis]))
+        });
+        max - min
+    });
+    let axis = (0..3)
+        .max_by(|&a, &b| spreads[a].total_cmp(&spreads[b]))
+        .expect("at least one axis");
+    points.sort_by(|(p1, _), (p2, _)| p1[axis].total_cmp(&p2[axis]));
+    let mid = points.len() / 2;
+    let right_points = points.split_off(mid + 1);
+    let (point, loc) = points.pop().expect("median exists");
+    let left_points = points;
+    Box::new(KdNode {
+        point,
+        loc,
+        axis,
+        left: (!left_points.is_empty()).then(|| build_kd_tree(left_points)),
+        right: (!right_points.is_empty()).then(|| build_kd_tree(right_points)),
+    })
+}
+
+fn collect_kd_tree(node: &KdNode, out: &mut Vec<(KdPoint, Location)>) {
+    out.push((node.point, node.loc));
+    if let Some(left) = &node.left {
+        collect_kd_tree(left, out);
+    }
+    if let Some(right) = &node.right {
+        collect_kd_tree(right, out);
+    }
+}
+
+fn kd_forest_insert(forest: &mut Vec<Option<Box<KdNode>>>, point: KdPoint, loc: Location) {
+    let mut carried = vec![(point, loc)];
+    for slot in forest.iter_mut() {
+        match slot.take() {
+            None => {
+                *slot = Some(build_kd_tree(carried));
+                return;
+            }
+            Some(tree) => collect_kd_tree(&tree, &mut carried),
+        }
+    }
+    forest.push(Some(build_kd_tree(carried)));
+}
+
+fn kd_tree_nearest(node: &KdNode, query: KdPoint, best: &mut Option<(f64, Location)>) {
+    let dist = kd_squared_dist(node.point, query);
+    if best.is_none_or(|(best_dist, _)| dist < best_dist) {
+        *best = Some((dist, node.loc));
+    }
+    let diff = query[node.axis] - node.point[node.axis];
+    let (near, far) = if diff < 0.0 {
+        (&node.left, &node.right)
+    } else {
+        (&node.right, &node.left)
+    };
+    if let Some(near) = near {
+        kd_tree_nearest(near, query, best);
+    }
+    if let Some(far) = far {
+        if best.is_none_or(|(best_dist, _)| diff.powi(2) < best_dist) {
+            kd_tree_nearest(far, query, best);
+        }
+    }
+}
+
+fn kd_forest_nearest(forest: &[Option<Box<KdNode>>], query: KdPoint) -> Option<Location> {
+    let mut best = None;
+    for tree in forest.iter().flatten() {
+        kd_tree_nearest(tree, query, &mut best);
+    }
+    best.map(|(_, loc)| loc)
 }
 
 fn remove_random<T, H, R>(set: &mut HashSet<T, H>, rng: &mut R) -> Option<T>
@@ -88,6 +369,155 @@ where
     std::array::from_fn::<(T, U), V, _>(|i| (a[i], b[i]))
 }
 
+fn render_grid(
+    grid: &[Vec<Option<ColorBase>>],
+    size: usize,
+    color_size: u64,
+    background: Color,
+) -> RgbImage {
+    let mut img: RgbImage = ImageBuffer::new(size as u32, size as u32);
+    for (i, row) in grid.iter().enumerate() {
+        for (j, color_base) in row.iter().enumerate() {
+            let pixel = color_base.map_or(background, |cb| color_base_to_color(cb, color_size));
+            img.put_pixel(i as u32, j as u32, image::Rgb(pixel));
+        }
+    }
+    img
+}
+
+// How a new color's location is chosen.
+#[derive(Clone, Copy)]
+enum PlacementStrategy {
+    // Walk a spiral out from the most similar already-placed color.
+    Spiral,
+    // Grow toward whichever empty frontier cell's neighborhood mean color
+    // is the best match, producing dense organic blooms.
+    MeanFrontier,
+}
+
+// The empty cells bordering already-filled ones, each tracking a running
+// sum of its filled 8-neighbors' colors so its mean can be compared
+// against a new color without rescanning the neighborhood every time.
+type Frontier = HashMap<Location, ([i32; 3], u16)>;
+
+// The 8 neighbors of `loc` on the wrap-around (torus) grid the spiral walk
+// already wraps onto.
+fn neighbors8(loc: Location, size: usize) -> impl Iterator<Item = Location> {
+    let wrap = move |v: usize, d: i64| (v as i64 + d).rem_euclid(size as i64) as usize;
+    (-1i64..=1)
+        .flat_map(|di| (-1i64..=1).map(move |dj| (di, dj)))
+        .filter(|&(di, dj)| (di, dj) != (0, 0))
+        .map(move |(di, dj)| [wrap(loc[0], di), wrap(loc[1], dj)])
+}
+
+fn update_frontier(
+    frontier: &mut Frontier,
+    grid: &[Vec<Option<ColorBase>>],
+    filled_loc: Location,
+    color_base: ColorBase,
+    size: usize,
+) {
+    frontier.remove(&filled_loc);
+    for neighbor in neighbors8(filled_loc, size) {
+        if grid[neighbor[0]][neighbor[1]].is_none() {
+            let (sum, count) = frontier.entry(neighbor).or_insert(([0; 3], 0));
+            for (s, c) in sum.iter_mut().zip(color_base) {
+                *s += c as i32;
+            }
+            *count += 1;
+        }
+    }
+}
+
+fn pick_frontier_location(
+    frontier: &Frontier,
+    color_base: ColorBase,
+    color_size: u64,
+    color_metric: ColorMetric,
+) -> Location {
+    let target = color_base_to_metric_point(color_base, color_size, color_metric);
+    frontier
+        .iter()
+        .map(|(&loc, &(sum, count))| {
+            let mean = sum.map(|s| s as f64 / count as f64);
+            let dist = kd_squared_dist(cube_point_to_metric(mean, color_size, color_metric), target);
+            (loc, dist)
+        })
+        .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+        .map(|(loc, _)| loc)
+        .expect("frontier is nonempty once any color is placed")
+}
+
+// How (and whether) to emit a time-lapse of the growth process alongside
+// the final image, by periodically snapshotting the partially filled grid.
+enum Animation {
+    None,
+    // Numbered PNGs, one per snapshot, written as `{prefix}NNNNNN.png`.
+    Frames { every: usize, prefix: String },
+    // A single animated GIF assembled from the snapshots.
+    Gif { every: usize, path: String },
+}
+
+struct Snapshotter {
+    animation: Animation,
+    background: Color,
+    frame_index: usize,
+    gif_encoder: Option<GifEncoder<File>>,
+}
+
+impl Snapshotter {
+    fn new(animation: Animation, background: Color) -> Self {
+        let gif_encoder = match &animation {
+            Animation::Gif { path, .. } => {
+                Some(GifEncoder::new(File::create(path).expect("create gif file")))
+            }
+            Animation::None | Animation::Frames { .. } => None,
+        };
+        Self {
+            animation,
+            background,
+            frame_index: 0,
+            gif_encoder,
+        }
+    }
+
+    fn maybe_capture(
+        &mut self,
+        grid: &[Vec<Option<ColorBase>>],
+        size: usize,
+        color_size: u64,
+        placements: usize,
+    ) {
+        let every = match &self.animation {
+            Animation::None => return,
+            Animation::Frames { every, .. } | Animation::Gif { every, .. } => *every,
+        };
+        if placements % every != 0 {
+            return;
+        }
+        let img = render_grid(grid, size, color_size, self.background);
+        match &self.animation {
+            Animation::Frames { prefix, .. } => {
+                img.save(format!("{prefix}{:06}.png", self.frame_index))
+                    .expect("save animation frame");
+            }
+            Animation::Gif { .. } => {
+                let rgba: RgbaImage = ImageBuffer::from_fn(size as u32, size as u32, |x, y| {
+                    let image::Rgb([r, g, b]) = *img.get_pixel(x, y);
+                    image::Rgba([r, g, b, 255])
+                });
+                self.gif_encoder
+                    .as_mut()
+                    .expect("gif mode carries an encoder")
+                    .encode_frame(Frame::new(rgba))
+                    .expect("encode animation frame");
+            }
+            Animation::None => unreachable!(),
+        }
+        self.frame_index += 1;
+    }
+}
+
 fn make_image(
     scale: u64,
     num_seeds: usize,
@@ -95,14 +525,27 @@ fn make_image(
     alpha: f64,
     cycle_cap: usize,
     seed: u64,
+    color_order: ColorOrder,
+    color_metric: ColorMetric,
+    direction_field: DirectionField,
+    placement_strategy: PlacementStrategy,
+    animation: Animation,
+    background: Color,
 ) -> RgbImage {
     let mut rng = StdRng::seed_from_u64(seed);
     let size = scale.pow(3) as usize;
     let color_size = scale.pow(2);
-    let (color_bases, color_offsets) = make_bases_offsets(scale, &mut rng);
+    let color_bases = make_bases(scale, color_order, &mut rng);
+    let perlin_field = match direction_field {
+        DirectionField::Random => None,
+        DirectionField::Perlin => Some(PerlinField::new(&mut rng, 4, 4)),
+    };
     let mut grid: Vec<Vec<Option<ColorBase>>> = vec![vec![None; size]; size];
     let mut initial_dirs: Vec<Vec<f64>> = vec![vec![0.0; size]; size];
-    let mut color_base_to_location: HashMap<ColorBase, Location> = HashMap::new();
+    let mut placed_colors: Vec<Option<Box<KdNode>>> = Vec::new();
+    let mut frontier: Frontier = HashMap::new();
+    let mut snapshotter = Snapshotter::new(animation, background);
+    let mut placements = 0usize;
     // Fixed hasher because we use the iteration order later
     let mut open_locs: HashSet<Location, BuildHasherDefault<XxHash64>> = (0..size)
         .flat_map(|i| (0..size).map(move |j| [i, j]))
@@ -112,24 +555,38 @@ fn make_image(
         if i < num_seeds {
             let loc = remove_random(&mut open_locs, &mut rng).expect("Don't over draw");
             grid[loc[0]][loc[1]] = Some(color_base);
-            initial_dirs[loc[0]][loc[1]] = rng.gen_range(0.0..TAU);
-            color_base_to_location.insert(color_base, loc);
+            match placement_strategy {
+                PlacementStrategy::Spiral => {
+                    initial_dirs[loc[0]][loc[1]] =
+                        sample_initial_dir(&perlin_field, loc, size, &mut rng);
+                    kd_forest_insert(
+                        &mut placed_colors,
+                        color_base_to_metric_point(color_base, color_size, color_metric),
+                        loc,
+                    );
+                }
+                PlacementStrategy::MeanFrontier => {
+                    update_frontier(&mut frontier, &grid, loc, color_base, size);
+                }
+            }
+            placements += 1;
+            snapshotter.maybe_capture(&grid, size, color_size, placements);
             continue;
         }
-        let most_similar_location: Location = color_offsets
-            .iter()
-            .filter_map(|color_offset| {
-                let prov_new_color_base =
-                    array_zip(color_base, *color_offset).map(|(c, co)| c as i16 + co);
-                if prov_new_color_base.iter().any(|&c| c < 0 || c > 255) {
-                    None
-                } else {
-                    let new_color_base = prov_new_color_base.map(|c| c as u8);
-                    color_base_to_location.get(&new_color_base).copied()
-                }
-            })
-            .next()
-            .expect("Seeded");
+        if let PlacementStrategy::MeanFrontier = placement_strategy {
+            let loc = pick_frontier_location(&frontier, color_base, color_size, color_metric);
+            grid[loc[0]][loc[1]] = Some(color_base);
+            update_frontier(&mut frontier, &grid, loc, color_base, size);
+            open_locs.remove(&loc);
+            placements += 1;
+            snapshotter.maybe_capture(&grid, size, color_size, placements);
+            continue;
+        }
+        let most_similar_location: Location = kd_forest_nearest(
+            &placed_colors,
+            color_base_to_metric_point(color_base, color_size, color_metric),
+        )
+        .expect("Seeded");
         let mut dir = initial_dirs[most_similar_location[0]][most_similar_location[1]];
         let mut loc = most_similar_location.map(|i| i as f64);
         for step in 1..cycle_cap*size {
@@ -145,7 +602,13 @@ fn make_image(
             if grid[pos[0]][pos[1]].is_none() {
                 grid[pos[0]][pos[1]] = Some(color_base);
                 initial_dirs[pos[0]][pos[1]] = dir;
-                color_base_to_location.insert(color_base, pos);
+                placements += 1;
+                snapshotter.maybe_capture(&grid, size, color_size, placements);
+                kd_forest_insert(
+                    &mut placed_colors,
+                    color_base_to_metric_point(color_base, color_size, color_metric),
+                    pos,
+                );
                 let was_present = open_locs.remove(&pos);
                 assert!(was_present);
                 continue 'main;
@@ -153,22 +616,16 @@ fn make_image(
         }
         let loc = remove_random(&mut open_locs, &mut rng).expect("Don't over draw later");
         grid[loc[0]][loc[1]] = Some(color_base);
-        initial_dirs[loc[0]][loc[1]] = rng.gen_range(0.0..TAU);
-        color_base_to_location.insert(color_base, loc);
-    }
-    let mut img: RgbImage = ImageBuffer::new(size as u32, size as u32);
-    for (i, row) in grid.into_iter().enumerate() {
-        for (j, color_base) in row.into_iter().enumerate() {
-            if let Some(color_base) = color_base {
-                img.put_pixel(
-                    i as u32,
-                    j as u32,
-                    image::Rgb(color_base_to_color(color_base, color_size)),
-                );
-            }
-        }
+        initial_dirs[loc[0]][loc[1]] = sample_initial_dir(&perlin_field, loc, size, &mut rng);
+        placements += 1;
+        snapshotter.maybe_capture(&grid, size, color_size, placements);
+        kd_forest_insert(
+            &mut placed_colors,
+            color_base_to_metric_point(color_base, color_size, color_metric),
+            loc,
+        );
     }
-    img
+    render_grid(&grid, size, color_size, background)
 }
 
 fn main() {
@@ -178,10 +635,29 @@ fn main() {
     let alpha = 0.2;
     let cycle_cap = 10;
     let seed = 0;
+    let color_order = ColorOrder::Shuffle;
+    let color_metric = ColorMetric::Rgb;
+    let direction_field = DirectionField::Random;
+    let placement_strategy = PlacementStrategy::Spiral;
+    let animation = Animation::None;
+    let background = [0, 0, 0];
     let filename = format!(
         "img-{scale}-{num_seeds}-{initial_turn_rate}-{alpha}-{cycle_cap}-{seed}.png"
         );
     println!("Start {filename}");
-    let img = make_image(scale, num_seeds, initial_turn_rate, alpha, cycle_cap, seed);
+    let img = make_image(
+        scale,
+        num_seeds,
+        initial_turn_rate,
+        alpha,
+        cycle_cap,
+        seed,
+        color_order,
+        color_metric,
+        direction_field,
+        placement_strategy,
+        animation,
+        background,
+    );
     img.save(&filename).unwrap();
 }